@@ -0,0 +1,3 @@
+pub(crate) mod task;
+
+pub(crate) mod schedule;
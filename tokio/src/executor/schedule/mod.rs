@@ -0,0 +1,3 @@
+mod throttle;
+#[cfg(feature = "rt-full")]
+pub(crate) use self::throttle::Throttle;
@@ -0,0 +1,40 @@
+use std::cell::Cell;
+
+thread_local! {
+    /// Remaining cooperative-scheduling budget for whatever task `Task::run`
+    /// is currently driving on this worker thread.
+    static REMAINING: Cell<usize> = Cell::new(0);
+}
+
+/// Default starting budget for a task's run, used when nothing more
+/// specific (e.g. a runtime-configured override) is supplied.
+///
+/// A caller that wants a different budget passes it directly to
+/// [`super::Task::run`]; until this crate grows a runtime `Builder`,
+/// overriding the default is a per-call argument rather than a
+/// process-wide setting.
+pub(crate) const DEFAULT_BUDGET: usize = 128;
+
+/// Reset this worker thread's budget to `initial`. Called once by
+/// `Task::run` before it starts driving a task, so every task gets a fresh
+/// allowance regardless of what the previous one left behind.
+pub(crate) fn reset(initial: usize) {
+    REMAINING.with(|cell| cell.set(initial));
+}
+
+/// Consume one unit of budget, as `Task::run`'s polling loop does each time
+/// a task would otherwise be polled again immediately because it woke
+/// itself. Returns `false` once exhausted, at which point the task must be
+/// handed back to the scheduler's queue instead of being driven further
+/// right now, so it cannot monopolize this worker.
+pub(crate) fn decrement() -> bool {
+    REMAINING.with(|cell| {
+        let remaining = cell.get();
+        if remaining == 0 {
+            false
+        } else {
+            cell.set(remaining - 1);
+            true
+        }
+    })
+}
@@ -0,0 +1,109 @@
+use crate::executor::task::join_waker::JoinWaker;
+use crate::executor::task::list::OwnedListLinks;
+use crate::executor::task::state::State;
+
+use std::cell::{Cell as StdCell, UnsafeCell};
+use std::future::Future;
+use std::ptr::NonNull;
+
+/// The part of a task's allocation shared by every handle to it (`Task`,
+/// `JoinHandle`, and any cloned `Waker`s). Its address is the task's
+/// erased, pointer-stable identity.
+///
+/// `Header` never depends on the task's future or scheduler types, which is
+/// what lets the raw waker vtable, `OwnedList` and `TransferStack` operate
+/// on it without knowing either.
+#[repr(C)]
+pub(crate) struct Header<Tag = ()> {
+    /// Lifecycle state and ref count, packed into a single word.
+    pub(crate) state: State,
+
+    /// Links for the executor's `OwnedList` of every task it owns.
+    pub(crate) owned: OwnedListLinks<Tag>,
+
+    /// Link used by `TransferStack` to hand the task to a run queue.
+    pub(crate) queue_next: StdCell<Option<NonNull<Header<Tag>>>>,
+
+    /// Waker registered by a `JoinHandle` awaiting the task's output.
+    /// Lives here, rather than alongside the future, so a `JoinHandle`
+    /// (which does not know the task's scheduler type) can reach it.
+    pub(crate) join_waker: JoinWaker,
+
+    /// Function pointers for the operations that still require static
+    /// knowledge of the task's erased future and scheduler types.
+    pub(crate) vtable: &'static Vtable<Tag>,
+
+    /// Caller-supplied metadata, readable for the life of the task.
+    pub(crate) tag: Tag,
+}
+
+/// Function pointers for task operations that require static knowledge of
+/// the erased future (`T`) and scheduler (`S`) types.
+///
+/// One `Vtable` is built per concrete `(T, S, M, Tag)` instantiation (see
+/// `raw::vtable`) and shared (`'static`) by every task of that shape.
+pub(crate) struct Vtable<Tag = ()> {
+    /// Poll the task's future once, consulting the calling worker's
+    /// cooperative scheduling budget if the task wakes itself while being
+    /// polled.
+    pub(crate) poll: unsafe fn(
+        NonNull<Header<Tag>>,
+        &mut dyn FnMut() -> Option<NonNull<()>>,
+    ) -> PollOutcome,
+
+    /// Re-schedule the task via the scheduler bound to it on first poll.
+    pub(crate) schedule: unsafe fn(NonNull<Header<Tag>>),
+
+    /// Drop the future without polling it to completion, then release it
+    /// as part of executor shutdown.
+    pub(crate) cancel: unsafe fn(NonNull<Header<Tag>>),
+
+    /// Move the task's stored output (or panic) into `dst`, which the
+    /// caller has prepared to hold `Result<T::Output>`.
+    pub(crate) read_output: unsafe fn(NonNull<Header<Tag>>, dst: *mut ()),
+
+    /// Drop the future/output and free the task's allocation. Called once
+    /// the ref count reaches zero.
+    pub(crate) dealloc: unsafe fn(NonNull<Header<Tag>>),
+}
+
+/// The scheduler binding and the in-progress (or completed) future.
+pub(crate) struct Core<T: Future, S> {
+    /// Bound once, the first time the task is polled, from the pointer
+    /// handed to `Task::run`'s `executor` callback.
+    pub(crate) scheduler: UnsafeCell<Option<NonNull<S>>>,
+    pub(crate) stage: UnsafeCell<Stage<T>>,
+}
+
+pub(crate) enum Stage<T: Future> {
+    Running(T),
+    Finished(super::Result<T::Output>),
+    Consumed,
+}
+
+/// Outcome of one `Vtable::poll` call, folding in whatever the calling
+/// worker's cooperative scheduling budget (see `super::budget`) had to say
+/// about a task that woke itself while being polled.
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub(crate) enum PollOutcome {
+    /// The future completed, panicked, or was cancelled, and the task has
+    /// already been released; or the task went idle, its reference now
+    /// held until a wake hands it back to the scheduler.
+    Done,
+    /// The task woke itself while being polled and there is still budget
+    /// left to poll it again immediately.
+    PollAgain,
+    /// The task woke itself while being polled, but the budget for this
+    /// turn is exhausted: it must be handed back to the scheduler's queue
+    /// instead of being polled again right now.
+    Requeue,
+}
+
+/// The complete task allocation. `Header` is first so that a
+/// `NonNull<Header<Tag>>` obtained from a `Box<Cell<..>>` can be cast back
+/// to `*mut Cell<..>`.
+#[repr(C)]
+pub(crate) struct Cell<T: Future, S, Tag = ()> {
+    pub(crate) header: Header<Tag>,
+    pub(crate) core: Core<T, S>,
+}
@@ -0,0 +1,59 @@
+use std::any::Any;
+use std::fmt;
+
+/// Task failed to complete.
+pub struct JoinError {
+    repr: Repr,
+}
+
+enum Repr {
+    Panic(Box<dyn Any + Send + 'static>),
+    Cancelled,
+}
+
+impl JoinError {
+    pub(crate) fn panic(err: Box<dyn Any + Send + 'static>) -> JoinError {
+        JoinError {
+            repr: Repr::Panic(err),
+        }
+    }
+
+    /// The task was cancelled, via `JoinHandle::abort` or by executor
+    /// shutdown, before it completed.
+    pub(crate) fn cancelled() -> JoinError {
+        JoinError {
+            repr: Repr::Cancelled,
+        }
+    }
+
+    /// Returns true if the task failed because it panicked.
+    pub fn is_panic(&self) -> bool {
+        matches!(self.repr, Repr::Panic(_))
+    }
+
+    /// Returns true if the task was cancelled, via `JoinHandle::abort` or by
+    /// executor shutdown.
+    pub fn is_cancelled(&self) -> bool {
+        matches!(self.repr, Repr::Cancelled)
+    }
+}
+
+impl fmt::Display for JoinError {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.repr {
+            Repr::Panic(_) => write!(fmt, "task panicked"),
+            Repr::Cancelled => write!(fmt, "task was cancelled"),
+        }
+    }
+}
+
+impl fmt::Debug for JoinError {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.repr {
+            Repr::Panic(_) => fmt.debug_struct("JoinError::Panic").finish(),
+            Repr::Cancelled => fmt.debug_struct("JoinError::Cancelled").finish(),
+        }
+    }
+}
+
+impl std::error::Error for JoinError {}
@@ -0,0 +1,216 @@
+use crate::executor::task::core::{Cell, Header, PollOutcome, Stage};
+use crate::executor::task::state::PollResult;
+use crate::executor::task::{budget, waker, JoinError, Schedule, Task};
+
+use std::future::Future;
+use std::mem;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::ptr::NonNull;
+use std::task::{Context, Poll};
+
+/// Poll the task's future once, lazily binding its scheduler on the first
+/// call. See `PollOutcome` for what the caller should do next.
+pub(crate) unsafe fn poll<T, S, M, Tag>(
+    header: NonNull<Header<Tag>>,
+    executor: &mut dyn FnMut() -> Option<NonNull<()>>,
+) -> PollOutcome
+where
+    T: Future,
+    S: Schedule<M, Tag>,
+    Tag: 'static,
+{
+    let cell = header.cast::<Cell<T, S, Tag>>();
+
+    let scheduler = match *cell.as_ref().core.scheduler.get() {
+        Some(ptr) => ptr,
+        None => {
+            let ptr = executor()
+                .expect("a scheduler must be available the first time a task is polled")
+                .cast::<S>();
+            *cell.as_ref().core.scheduler.get() = Some(ptr);
+            scheduler_ref(cell).bind(&Task::<S, M, Tag>::from_raw(header));
+            ptr
+        }
+    };
+    let scheduler = scheduler.as_ref();
+
+    let prev = cell.as_ref().header.state.start_poll();
+
+    if prev.is_closed() {
+        // `JoinHandle::abort` closed the task while it sat idle in a
+        // scheduler queue: drop the future without polling it at all.
+        if let PollResult::Closed = cell.as_ref().header.state.complete_poll(true) {
+            finish_closed::<T, S, Tag>(cell);
+        }
+        complete::<T, S, M, Tag>(cell, scheduler);
+        return PollOutcome::Done;
+    }
+
+    let waker = waker::waker(header);
+    let mut cx = Context::from_waker(&waker);
+
+    let poll = {
+        let stage = &mut *cell.as_ref().core.stage.get();
+        let fut = match stage {
+            Stage::Running(fut) => Pin::new_unchecked(fut),
+            _ => unreachable!("task polled after completion"),
+        };
+        panic::catch_unwind(AssertUnwindSafe(|| fut.poll(&mut cx)))
+    };
+
+    // `JoinHandle::abort` may have closed the task while the poll above was
+    // in progress (e.g. the future aborted itself, or another thread raced
+    // with this poll). Letting the poll above run to completion keeps the
+    // future's own invariants intact; we only decide what to do with the
+    // result here. Whether the task was closed is read from the result of
+    // `complete_poll` itself below, not from a separate load taken now: a
+    // separately-timed load would leave a window, between here and the
+    // transition that actually clears `RUNNING`, for a concurrent `abort`
+    // to land in and be missed, stranding the task instead of cancelling it.
+    //
+    // The Ready/panic arms write `stage` to the future's real outcome
+    // *before* calling `complete_poll`, because that call may directly
+    // publish `COMPLETE` (the common, not-closed case) and a write to
+    // `stage` after `COMPLETE` is visible would race with a concurrent
+    // `read_output`. If `complete_poll` instead reports `Closed`, it has
+    // published `FINALIZING`, not `COMPLETE` -- so overwriting `stage` with
+    // the cancelled output here is still safe, and `finish_cancel` is what
+    // actually publishes completion afterwards.
+    match poll {
+        Ok(Poll::Ready(output)) => {
+            *cell.as_ref().core.stage.get() = Stage::Finished(Ok(output));
+            if let PollResult::Closed = cell.as_ref().header.state.complete_poll(true) {
+                finish_closed::<T, S, Tag>(cell);
+            }
+            complete::<T, S, M, Tag>(cell, scheduler);
+            PollOutcome::Done
+        }
+        Ok(Poll::Pending) => match cell.as_ref().header.state.complete_poll(false) {
+            PollResult::Closed => {
+                // `complete_poll` already set `FINALIZING`, not `COMPLETE`:
+                // dropping the pending future and writing the cancelled
+                // output here can't race with a reader, and `finish_cancel`
+                // is what finally makes the task observably complete.
+                finish_closed::<T, S, Tag>(cell);
+                complete::<T, S, M, Tag>(cell, scheduler);
+                PollOutcome::Done
+            }
+            PollResult::Yielded => {
+                if budget::decrement() {
+                    PollOutcome::PollAgain
+                } else {
+                    PollOutcome::Requeue
+                }
+            }
+            PollResult::Idle => PollOutcome::Done,
+            PollResult::Complete => unreachable!("complete_poll(false) cannot report Complete"),
+        },
+        Err(panic) => {
+            *cell.as_ref().core.stage.get() = Stage::Finished(Err(
+                crate::executor::task::error_from_panic(panic),
+            ));
+            if let PollResult::Closed = cell.as_ref().header.state.complete_poll(true) {
+                finish_closed::<T, S, Tag>(cell);
+            }
+            complete::<T, S, M, Tag>(cell, scheduler);
+            PollOutcome::Done
+        }
+    }
+}
+
+/// Write a task's output as cancelled and publish its completion, once a
+/// poll has already observed `PollResult::Closed` from `complete_poll`
+/// (which left `FINALIZING`, not `COMPLETE`, set). Used whenever a closed
+/// task is finished: skipped before ever being polled, found closed right
+/// after a `Pending` poll, or overriding a `Ready`/panic outcome that raced
+/// with an `abort`.
+unsafe fn finish_closed<T, S, Tag>(cell: NonNull<Cell<T, S, Tag>>)
+where
+    T: Future,
+    Tag: 'static,
+{
+    *cell.as_ref().core.stage.get() = Stage::Finished(Err(JoinError::cancelled()));
+    cell.as_ref().header.state.finish_cancel();
+}
+
+fn scheduler_ref<T: Future, S, Tag>(cell: NonNull<Cell<T, S, Tag>>) -> &'static S {
+    unsafe { (*cell.as_ref().core.scheduler.get()).unwrap().as_ref() }
+}
+
+/// Re-schedule the task via the scheduler bound to it on first poll. Called
+/// from the waker vtable when it takes responsibility for a wake.
+pub(crate) unsafe fn schedule<T, S, M, Tag>(header: NonNull<Header<Tag>>)
+where
+    T: Future,
+    S: Schedule<M, Tag>,
+    Tag: 'static,
+{
+    let cell = header.cast::<Cell<T, S, Tag>>();
+    let scheduler = (*cell.as_ref().core.scheduler.get())
+        .expect("scheduler not yet bound")
+        .as_ref();
+    scheduler.schedule(Task::from_raw(header));
+}
+
+/// Drop the future in place, without polling it to completion, and release
+/// the task as part of executor shutdown.
+pub(crate) unsafe fn cancel<T, S, M, Tag>(header: NonNull<Header<Tag>>)
+where
+    T: Future,
+    S: Schedule<M, Tag>,
+    Tag: 'static,
+{
+    let cell = header.cast::<Cell<T, S, Tag>>();
+    *cell.as_ref().core.stage.get() = Stage::Finished(Err(JoinError::cancelled()));
+    // Shutdown has exclusive access to every task it still owns: no poll or
+    // wake can be racing this, so there's no `FINALIZING` hand-off to honor
+    // here, unlike `complete_poll`.
+    cell.as_ref().header.state.force_complete();
+
+    match *cell.as_ref().core.scheduler.get() {
+        Some(scheduler) => complete::<T, S, M, Tag>(cell, scheduler.as_ref()),
+        None => {
+            if cell.as_ref().header.state.ref_dec() {
+                dealloc::<T, S, Tag>(header);
+            }
+        }
+    }
+}
+
+/// Move the task's stored output (or panic) into `dst`, which the caller
+/// has prepared to hold `Result<T::Output>`.
+pub(crate) unsafe fn read_output<T, S, Tag>(header: NonNull<Header<Tag>>, dst: *mut ())
+where
+    T: Future,
+    Tag: 'static,
+{
+    let cell = header.cast::<Cell<T, S, Tag>>();
+    let stage = mem::replace(&mut *cell.as_ref().core.stage.get(), Stage::Consumed);
+    let output = match stage {
+        Stage::Finished(output) => output,
+        _ => panic!("task output missing"),
+    };
+    (dst as *mut crate::executor::task::Result<T::Output>).write(output);
+}
+
+/// Drop the future/output and free the task's allocation.
+pub(crate) unsafe fn dealloc<T, S, Tag>(header: NonNull<Header<Tag>>)
+where
+    T: Future,
+{
+    drop(Box::from_raw(header.cast::<Cell<T, S, Tag>>().as_ptr()));
+}
+
+unsafe fn complete<T, S, M, Tag>(cell: NonNull<Cell<T, S, Tag>>, scheduler: &S)
+where
+    T: Future,
+    S: Schedule<M, Tag>,
+    Tag: 'static,
+{
+    cell.as_ref().header.join_waker.wake();
+
+    let task = Task::<S, M, Tag>::from_raw(cell.cast());
+    scheduler.release_local(&task);
+    scheduler.release(task);
+}
@@ -0,0 +1,103 @@
+use crate::executor::task::raw::RawTask;
+use crate::executor::task::Result;
+
+use std::fmt;
+use std::future::Future;
+use std::marker::PhantomData;
+use std::mem::MaybeUninit;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// An owned permission to join (await the output of) a spawned task.
+///
+/// A `JoinHandle` is returned by `joinable`/`joinable_unsend` alongside the
+/// `Task` handed to the scheduler. Awaiting it resolves to the task's
+/// output once the task completes, or to a `JoinError` if it panicked or
+/// was cancelled.
+#[cfg(any(feature = "rt-current-thread", feature = "rt-full"))]
+pub struct JoinHandle<T, Tag = ()> {
+    raw: RawTask<Tag>,
+    _p: PhantomData<fn() -> T>,
+}
+
+#[cfg(any(feature = "rt-current-thread", feature = "rt-full"))]
+unsafe impl<T: Send, Tag: Send + Sync + 'static> Send for JoinHandle<T, Tag> {}
+
+#[cfg(any(feature = "rt-current-thread", feature = "rt-full"))]
+impl<T, Tag> JoinHandle<T, Tag> {
+    pub(crate) fn new(raw: RawTask<Tag>) -> JoinHandle<T, Tag> {
+        JoinHandle {
+            raw,
+            _p: PhantomData,
+        }
+    }
+
+    /// Returns the metadata ("tag") the task was spawned with.
+    pub fn tag(&self) -> &Tag {
+        &self.raw.header().tag
+    }
+
+    /// Cancel the task, cooperatively.
+    ///
+    /// If the task has already completed, this has no effect. Otherwise,
+    /// the task is marked closed: a task currently being polled is allowed
+    /// to finish that poll and is then released as usual, while a task
+    /// sitting idle is woken so that it is dropped the next time it would
+    /// have been polled. Either way, awaiting this handle afterwards
+    /// resolves to `Err(JoinError::cancelled())` instead of the task's
+    /// actual output.
+    pub fn abort(&self) {
+        let header = self.raw.header();
+        let prev = header.state.close();
+
+        // If the task is currently being polled, let that poll finish; it
+        // will notice the closed bit itself once it returns. Otherwise, if
+        // nothing has it scheduled, we must schedule it ourselves so it is
+        // dropped instead of sitting idle forever.
+        if !prev.is_running() && header.state.transition_to_scheduled() {
+            unsafe { (header.vtable.schedule)(self.raw.into_raw()) };
+        }
+    }
+}
+
+#[cfg(any(feature = "rt-current-thread", feature = "rt-full"))]
+impl<T, Tag> Future for JoinHandle<T, Tag> {
+    type Output = Result<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<T>> {
+        let header = self.raw.header();
+
+        if !header.state.load().is_complete() {
+            header.join_waker.register(cx.waker());
+
+            // The task may have completed between the check above and the
+            // waker being installed; re-check before committing to Pending.
+            // If it completes after this re-check, its `join_waker.wake()`
+            // finds the waker we just installed and wakes it, so returning
+            // `Pending` here is still correct.
+            if !header.state.load().is_complete() {
+                return Poll::Pending;
+            }
+        }
+
+        let mut out = MaybeUninit::<Result<T>>::uninit();
+        unsafe {
+            (header.vtable.read_output)(self.raw.into_raw(), out.as_mut_ptr() as *mut ());
+            Poll::Ready(out.assume_init())
+        }
+    }
+}
+
+#[cfg(any(feature = "rt-current-thread", feature = "rt-full"))]
+impl<T, Tag> Drop for JoinHandle<T, Tag> {
+    fn drop(&mut self) {
+        self.raw.drop_task();
+    }
+}
+
+#[cfg(any(feature = "rt-current-thread", feature = "rt-full"))]
+impl<T, Tag> fmt::Debug for JoinHandle<T, Tag> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("JoinHandle").finish()
+    }
+}
@@ -0,0 +1,84 @@
+use std::cell::UnsafeCell;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::task::Waker;
+
+const WAITING: u8 = 0b00;
+const REGISTERING: u8 = 0b01;
+const WOKEN: u8 = 0b10;
+
+/// The single `Waker` slot a `JoinHandle` registers interest through,
+/// guarded by a small CAS state machine instead of a bare store/take: a
+/// plain `UnsafeCell<Option<Waker>>` here would let `JoinHandle::poll`
+/// (registering) and `harness::complete` (waking) write the same
+/// non-atomic memory from two different threads with nothing ordering them
+/// against each other. Mirrors `futures`' `AtomicWaker`.
+pub(crate) struct JoinWaker {
+    state: AtomicU8,
+    waker: UnsafeCell<Option<Waker>>,
+}
+
+unsafe impl Send for JoinWaker {}
+unsafe impl Sync for JoinWaker {}
+
+impl JoinWaker {
+    pub(crate) fn new() -> JoinWaker {
+        JoinWaker {
+            state: AtomicU8::new(WAITING),
+            waker: UnsafeCell::new(None),
+        }
+    }
+
+    /// Register `waker` to be woken by the next `wake` call. If a wake is
+    /// in progress when this is called, or lands while this call is storing
+    /// the waker, the passed-in waker is woken immediately instead of being
+    /// left parked,
+    /// so a completion racing with registration is never missed.
+    pub(crate) fn register(&self, waker: &Waker) {
+        match self
+            .state
+            .compare_exchange(WAITING, REGISTERING, Ordering::Acquire, Ordering::Acquire)
+        {
+            Ok(_) => {
+                unsafe {
+                    *self.waker.get() = Some(waker.clone());
+                }
+                match self.state.compare_exchange(
+                    REGISTERING,
+                    WAITING,
+                    Ordering::AcqRel,
+                    Ordering::Acquire,
+                ) {
+                    Ok(_) => {}
+                    Err(_) => {
+                        // `wake` landed while we were storing the waker:
+                        // take it back and wake it ourselves instead of
+                        // leaving an already-woken task's waker parked.
+                        let waker = unsafe { (*self.waker.get()).take() };
+                        self.state.store(WAITING, Ordering::Release);
+                        if let Some(waker) = waker {
+                            waker.wake();
+                        }
+                    }
+                }
+            }
+            Err(_) => {
+                // Already woken, or (impossible with a single `JoinHandle`)
+                // another registration is in flight: either way, nothing
+                // left to store here, but the caller must not simply go to
+                // sleep on it.
+                waker.wake_by_ref();
+            }
+        }
+    }
+
+    /// Wake whatever waker is currently registered, if any.
+    pub(crate) fn wake(&self) {
+        if self.state.swap(WOKEN, Ordering::AcqRel) == WAITING {
+            let waker = unsafe { (*self.waker.get()).take() };
+            self.state.store(WAITING, Ordering::Release);
+            if let Some(waker) = waker {
+                waker.wake();
+            }
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use crate::executor::task::core::Header;
+
+use std::cell::Cell;
+use std::ptr::NonNull;
+
+/// Intrusive doubly-linked-list links embedded in every task `Header`,
+/// letting an executor track every task it owns without a separate
+/// allocation per task.
+pub(crate) struct OwnedListLinks<Tag = ()> {
+    prev: Cell<Option<NonNull<Header<Tag>>>>,
+    next: Cell<Option<NonNull<Header<Tag>>>>,
+}
+
+impl<Tag> OwnedListLinks<Tag> {
+    pub(crate) fn new() -> OwnedListLinks<Tag> {
+        OwnedListLinks {
+            prev: Cell::new(None),
+            next: Cell::new(None),
+        }
+    }
+}
+
+/// The set of every task owned by an executor, linked through
+/// `Header::owned`.
+///
+/// Used so that, on shutdown, the executor can walk every outstanding task
+/// and cancel it without maintaining a separate `Vec`.
+pub(crate) struct OwnedList<Tag = ()> {
+    head: Option<NonNull<Header<Tag>>>,
+}
+
+impl<Tag> OwnedList<Tag> {
+    pub(crate) fn new() -> OwnedList<Tag> {
+        OwnedList { head: None }
+    }
+
+    /// Insert `task` at the head of the list.
+    ///
+    /// # Safety
+    ///
+    /// `task` must point at a live task that is not already linked into a
+    /// list.
+    pub(crate) unsafe fn push(&mut self, task: NonNull<Header<Tag>>) {
+        let links = &task.as_ref().owned;
+        links.next.set(self.head);
+        links.prev.set(None);
+
+        if let Some(head) = self.head {
+            head.as_ref().owned.prev.set(Some(task));
+        }
+
+        self.head = Some(task);
+    }
+
+    /// Remove `task` from the list.
+    ///
+    /// # Safety
+    ///
+    /// `task` must point at a live task currently linked into this list.
+    pub(crate) unsafe fn remove(&mut self, task: NonNull<Header<Tag>>) {
+        let links = &task.as_ref().owned;
+
+        match links.prev.get() {
+            Some(prev) => prev.as_ref().owned.next.set(links.next.get()),
+            None => self.head = links.next.get(),
+        }
+
+        if let Some(next) = links.next.get() {
+            next.as_ref().owned.prev.set(links.prev.get());
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
+}
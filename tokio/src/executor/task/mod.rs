@@ -1,5 +1,9 @@
+mod budget;
+pub(crate) use self::budget::DEFAULT_BUDGET;
+
 mod core;
 pub(crate) use self::core::Header;
+use self::core::PollOutcome;
 
 mod error;
 #[allow(unreachable_pub)] // https://github.com/rust-lang/rust/issues/57411
@@ -12,6 +16,8 @@ mod join;
 #[allow(unreachable_pub)] // https://github.com/rust-lang/rust/issues/57411
 pub use self::join::JoinHandle;
 
+mod join_waker;
+
 mod list;
 pub(crate) use self::list::OwnedList;
 
@@ -34,15 +40,19 @@ use std::marker::PhantomData;
 use std::ptr::NonNull;
 use std::{fmt, mem};
 
-/// An owned handle to the task, tracked by ref count
-pub(crate) struct Task<S: 'static, M = SendMarker> {
-    raw: RawTask,
+/// An owned handle to the task, tracked by ref count.
+///
+/// `Tag` is caller-supplied metadata stored alongside the task for its
+/// whole lifetime (see `background`/`joinable`), readable through
+/// `header()` without needing a `JoinHandle`.
+pub(crate) struct Task<S: 'static, M = SendMarker, Tag = ()> {
+    raw: RawTask<Tag>,
     _p: PhantomData<(S, M)>,
 }
 
 /// An owned handle to a `!Send` task, tracked by ref count.
 #[cfg(feature = "local")]
-pub(crate) type UnsendTask<S> = Task<S, UnsendMarker>;
+pub(crate) type UnsendTask<S, Tag = ()> = Task<S, UnsendMarker, Tag>;
 
 /// Marker type indicating that a `Task` was constructed from a future that
 /// implements `Send`.
@@ -56,47 +66,54 @@ pub(crate) struct SendMarker {}
 #[cfg(feature = "local")]
 pub(crate) struct UnsendMarker {}
 
-unsafe impl<S: Send + Sync + 'static> Send for Task<S, SendMarker> {}
+unsafe impl<S: Send + Sync + 'static, Tag: Send + Sync + 'static> Send for Task<S, SendMarker, Tag> {}
 
 /// Task result sent back
 pub(crate) type Result<T> = std::result::Result<T, JoinError>;
 
-pub(crate) trait Schedule<M>: Send + Sync + Sized + 'static {
+pub(crate) trait Schedule<M, Tag = ()>: Send + Sync + Sized + 'static {
     /// Bind a task to the executor.
     ///
     /// Guaranteed to be called from the thread that called `poll` on the task.
-    fn bind(&self, task: &Task<Self, M>);
+    fn bind(&self, task: &Task<Self, M, Tag>);
 
     /// The task has completed work and is ready to be released. The scheduler
     /// is free to drop it whenever.
-    fn release(&self, task: Task<Self, M>);
+    fn release(&self, task: Task<Self, M, Tag>);
 
     /// The has been completed by the executor it was bound to.
-    fn release_local(&self, task: &Task<Self, M>);
+    fn release_local(&self, task: &Task<Self, M, Tag>);
 
     /// Schedule the task
-    fn schedule(&self, task: Task<Self, M>);
+    fn schedule(&self, task: Task<Self, M, Tag>);
 }
 
-/// Create a new task without an associated join handle
-pub(crate) fn background<T, S>(task: T) -> Task<S>
+/// Create a new task without an associated join handle, carrying `tag` as
+/// its metadata for the task's whole lifetime.
+pub(crate) fn background<T, S, Tag>(task: T, tag: Tag) -> Task<S, SendMarker, Tag>
 where
     T: Future + Send + 'static,
-    S: Schedule<SendMarker>,
+    S: Schedule<SendMarker, Tag>,
+    Tag: Send + Sync + 'static,
 {
     Task {
-        raw: RawTask::new_background::<_, S>(task),
+        raw: RawTask::new_background::<_, S, SendMarker>(task, tag),
         _p: PhantomData,
     }
 }
 
-/// Create a new task with an associated join handle
-pub(crate) fn joinable<T, S>(task: T) -> (Task<S>, JoinHandle<T::Output>)
+/// Create a new task with an associated join handle, carrying `tag` as its
+/// metadata for the task's whole lifetime.
+pub(crate) fn joinable<T, S, Tag>(
+    task: T,
+    tag: Tag,
+) -> (Task<S, SendMarker, Tag>, JoinHandle<T::Output, Tag>)
 where
     T: Future + Send + 'static,
-    S: Schedule<SendMarker>,
+    S: Schedule<SendMarker, Tag>,
+    Tag: Send + Sync + 'static,
 {
-    let raw = RawTask::new_joinable::<_, S>(task);
+    let raw = RawTask::new_joinable::<_, S, SendMarker>(task, tag);
 
     let task = Task {
         raw,
@@ -108,14 +125,19 @@ where
     (task, join)
 }
 
-/// Create a new `!Send` task with an associated join handle
+/// Create a new `!Send` task with an associated join handle, carrying `tag`
+/// as its metadata for the task's whole lifetime.
 #[cfg(feature = "local")]
-pub(crate) fn joinable_unsend<T, S>(task: T) -> (UnsendTask<S>, JoinHandle<T::Output>)
+pub(crate) fn joinable_unsend<T, S, Tag>(
+    task: T,
+    tag: Tag,
+) -> (UnsendTask<S, Tag>, JoinHandle<T::Output, Tag>)
 where
     T: Future + 'static,
-    S: Schedule<UnsendMarker>,
+    S: Schedule<UnsendMarker, Tag>,
+    Tag: Send + Sync + 'static,
 {
-    let raw = RawTask::new_joinable_unsend::<_, S>(task);
+    let raw = RawTask::new_joinable_unsend::<_, S, UnsendMarker>(task, tag);
 
     let task = Task {
         raw,
@@ -127,41 +149,57 @@ where
     (task, join)
 }
 
-impl<S: 'static, M> Task<S, M> {
-    pub(crate) unsafe fn from_raw(ptr: NonNull<Header>) -> Task<S, M> {
+impl<S: 'static, M, Tag: 'static> Task<S, M, Tag> {
+    pub(crate) unsafe fn from_raw(ptr: NonNull<Header<Tag>>) -> Task<S, M, Tag> {
         Task {
             raw: RawTask::from_raw(ptr),
             _p: PhantomData,
         }
     }
 
-    pub(crate) fn header(&self) -> &Header {
+    /// Returns the task's header, whose `tag` metadata is readable for the
+    /// task's whole lifetime regardless of whether a `JoinHandle` is still
+    /// held.
+    pub(crate) fn header(&self) -> &Header<Tag> {
         self.raw.header()
     }
 
-    pub(crate) fn into_raw(self) -> NonNull<Header> {
+    pub(crate) fn into_raw(self) -> NonNull<Header<Tag>> {
         let raw = self.raw.into_raw();
         mem::forget(self);
         raw
     }
 }
 
-impl<S: Schedule<M>, M> Task<S, M> {
-    /// Returns `self` when the task needs to be immediately re-scheduled
-    pub(crate) fn run<F>(self, mut executor: F) -> Option<Self>
+impl<S: Schedule<M, Tag>, M, Tag: 'static> Task<S, M, Tag> {
+    /// Drive the task, honoring a cooperative scheduling budget of
+    /// `budget` immediate re-polls after the first, unconditional poll: a
+    /// task that keeps waking itself (e.g. a busy socket
+    /// with data always ready) is polled again immediately as long as
+    /// budget remains, but once it runs out the task is handed back to the
+    /// caller to be re-enqueued instead of continuing to monopolize this
+    /// worker. Returns `Some(self)` when the caller must re-schedule the
+    /// task, or `None` once it has gone idle or been released.
+    pub(crate) fn run<F>(self, budget: usize, mut executor: F) -> Option<Self>
     where
         F: FnMut() -> Option<NonNull<S>>,
     {
-        if unsafe {
-            self.raw
-                .poll(&mut || executor().map(|ptr| ptr.cast::<()>()))
-        } {
-            Some(self)
-        } else {
-            // Cleaning up the `Task` instance is done from within the poll
-            // function.
-            mem::forget(self);
-            None
+        self::budget::reset(budget);
+
+        loop {
+            match unsafe {
+                self.raw
+                    .poll(&mut || executor().map(|ptr| ptr.cast::<()>()))
+            } {
+                PollOutcome::Done => {
+                    // Cleaning up the `Task` instance is done from within
+                    // the poll function.
+                    mem::forget(self);
+                    return None;
+                }
+                PollOutcome::PollAgain => continue,
+                PollOutcome::Requeue => return Some(self),
+            }
         }
     }
 
@@ -172,16 +210,22 @@ impl<S: Schedule<M>, M> Task<S, M> {
     }
 }
 
-impl<S: 'static, M> Drop for Task<S, M> {
+impl<S: 'static, M, Tag: 'static> Drop for Task<S, M, Tag> {
     fn drop(&mut self) {
         self.raw.drop_task();
     }
 }
 
-impl<S, M> fmt::Debug for Task<S, M> {
+impl<S, M, Tag> fmt::Debug for Task<S, M, Tag> {
     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
         fmt.debug_struct("Task")
             .field("send", &format_args!("{}", std::any::type_name::<M>()))
             .finish()
     }
 }
+
+/// Wrap a caught panic payload as a `JoinError`, used by the polling
+/// harness when a task's future panics.
+pub(crate) fn error_from_panic(payload: Box<dyn std::any::Any + Send + 'static>) -> JoinError {
+    JoinError::panic(payload)
+}
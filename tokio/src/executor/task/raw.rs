@@ -0,0 +1,129 @@
+use crate::executor::task::core::{Cell, Core, Header, PollOutcome, Stage, Vtable};
+use crate::executor::task::join_waker::JoinWaker;
+use crate::executor::task::list::OwnedListLinks;
+use crate::executor::task::state::State;
+use crate::executor::task::{harness, Schedule};
+
+use std::cell::{Cell as StdCell, UnsafeCell};
+use std::future::Future;
+use std::ptr::NonNull;
+
+/// A type-erased, reference-counted pointer to a task allocation.
+pub(crate) struct RawTask<Tag = ()> {
+    ptr: NonNull<Header<Tag>>,
+}
+
+impl<Tag> Clone for RawTask<Tag> {
+    fn clone(&self) -> RawTask<Tag> {
+        RawTask { ptr: self.ptr }
+    }
+}
+
+impl<Tag> Copy for RawTask<Tag> {}
+
+impl<Tag> RawTask<Tag> {
+    pub(crate) fn new_background<T, S, M>(task: T, tag: Tag) -> RawTask<Tag>
+    where
+        T: Future + Send + 'static,
+        S: Schedule<M, Tag>,
+        Tag: Send + Sync + 'static,
+    {
+        RawTask::new::<T, S, M>(task, tag, State::new_background())
+    }
+
+    pub(crate) fn new_joinable<T, S, M>(task: T, tag: Tag) -> RawTask<Tag>
+    where
+        T: Future + Send + 'static,
+        S: Schedule<M, Tag>,
+        Tag: Send + Sync + 'static,
+    {
+        RawTask::new::<T, S, M>(task, tag, State::new_joinable())
+    }
+
+    #[cfg(feature = "local")]
+    pub(crate) fn new_joinable_unsend<T, S, M>(task: T, tag: Tag) -> RawTask<Tag>
+    where
+        T: Future + 'static,
+        S: Schedule<M, Tag>,
+        Tag: Send + Sync + 'static,
+    {
+        RawTask::new::<T, S, M>(task, tag, State::new_joinable())
+    }
+
+    fn new<T, S, M>(task: T, tag: Tag, state: State) -> RawTask<Tag>
+    where
+        T: Future + 'static,
+        S: Schedule<M, Tag>,
+        Tag: Send + Sync + 'static,
+    {
+        let cell = Box::new(Cell::<T, S, Tag> {
+            header: Header {
+                state,
+                owned: OwnedListLinks::new(),
+                queue_next: StdCell::new(None),
+                join_waker: JoinWaker::new(),
+                vtable: vtable::<T, S, M, Tag>(),
+                tag,
+            },
+            core: Core {
+                scheduler: UnsafeCell::new(None),
+                stage: UnsafeCell::new(Stage::Running(task)),
+            },
+        });
+
+        let ptr = unsafe { NonNull::new_unchecked(Box::into_raw(cell)).cast::<Header<Tag>>() };
+
+        RawTask { ptr }
+    }
+
+    pub(crate) unsafe fn from_raw(ptr: NonNull<Header<Tag>>) -> RawTask<Tag> {
+        RawTask { ptr }
+    }
+
+    pub(crate) fn header(&self) -> &Header<Tag> {
+        unsafe { self.ptr.as_ref() }
+    }
+
+    pub(crate) fn into_raw(self) -> NonNull<Header<Tag>> {
+        self.ptr
+    }
+
+    /// Poll the task's future once. See `PollOutcome` for what the caller
+    /// should do next.
+    pub(crate) unsafe fn poll(
+        &self,
+        executor: &mut dyn FnMut() -> Option<NonNull<()>>,
+    ) -> PollOutcome {
+        (self.header().vtable.poll)(self.ptr, executor)
+    }
+
+    /// Pre-emptively drop the future without polling it, as part of
+    /// executor shutdown.
+    pub(crate) fn cancel_from_queue(&self) {
+        unsafe { (self.header().vtable.cancel)(self.ptr) };
+    }
+
+    /// Release the reference represented by this `RawTask`, deallocating
+    /// the task if it was the last one.
+    pub(crate) fn drop_task(&self) {
+        if self.header().state.ref_dec() {
+            unsafe { (self.header().vtable.dealloc)(self.ptr) };
+        }
+    }
+}
+
+fn vtable<T, S, M, Tag>() -> &'static Vtable<Tag>
+where
+    T: Future + 'static,
+    S: Schedule<M, Tag>,
+    Tag: Send + Sync + 'static,
+{
+    const VTABLE: Vtable<Tag> = Vtable {
+        poll: harness::poll::<T, S, M, Tag>,
+        schedule: harness::schedule::<T, S, M, Tag>,
+        cancel: harness::cancel::<T, S, M, Tag>,
+        read_output: harness::read_output::<T, S, Tag>,
+        dealloc: harness::dealloc::<T, S, Tag>,
+    };
+    &VTABLE
+}
@@ -0,0 +1,55 @@
+use crate::executor::task::core::Header;
+
+use std::ptr;
+use std::ptr::NonNull;
+use std::sync::atomic::{AtomicPtr, Ordering};
+
+/// A lock-free, intrusive last-in-first-out stack of tasks, linked through
+/// `Header::queue_next`.
+///
+/// Used to hand a batch of tasks from one thread (e.g. a waker firing) to
+/// another (the worker that will actually poll them) without a heap
+/// allocation per transfer.
+pub(crate) struct TransferStack<Tag = ()> {
+    head: AtomicPtr<Header<Tag>>,
+}
+
+impl<Tag> TransferStack<Tag> {
+    pub(crate) fn new() -> TransferStack<Tag> {
+        TransferStack {
+            head: AtomicPtr::new(ptr::null_mut()),
+        }
+    }
+
+    /// Push `task` onto the stack.
+    ///
+    /// # Safety
+    ///
+    /// `task` must point at a live task not already linked into another
+    /// `TransferStack`.
+    pub(crate) unsafe fn push(&self, task: NonNull<Header<Tag>>) {
+        let mut curr = self.head.load(Ordering::Relaxed);
+        loop {
+            task.as_ref().queue_next.set(NonNull::new(curr));
+
+            match self
+                .head
+                .compare_exchange_weak(curr, task.as_ptr(), Ordering::Release, Ordering::Relaxed)
+            {
+                Ok(_) => return,
+                Err(actual) => curr = actual,
+            }
+        }
+    }
+
+    /// Take every task currently on the stack, in LIFO order.
+    pub(crate) fn drain(&self) -> impl Iterator<Item = NonNull<Header<Tag>>> {
+        let mut curr = NonNull::new(self.head.swap(ptr::null_mut(), Ordering::Acquire));
+
+        std::iter::from_fn(move || {
+            let task = curr?;
+            curr = unsafe { task.as_ref().queue_next.get() };
+            Some(task)
+        })
+    }
+}
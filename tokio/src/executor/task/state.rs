@@ -0,0 +1,283 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// The task is currently being polled.
+const RUNNING: usize = 0b0001;
+/// The task's future has finished (or been cancelled) and its output is
+/// either stored or has already been consumed.
+const COMPLETE: usize = 0b0010;
+/// The task is sitting in a run queue (or about to be), waiting to be
+/// polled.
+const SCHEDULED: usize = 0b0100;
+/// `JoinHandle::abort` was called: the next time the task is polled (or, if
+/// it is idle, the next time it is scheduled) it must be cancelled instead
+/// of driven to completion normally.
+const CLOSED: usize = 0b1000;
+/// A poll has found the task closed and has committed to cancelling it, but
+/// has not yet finished writing the cancelled output: set in the same CAS
+/// that clears `RUNNING` so the task never appears idle-and-unclaimed while
+/// in this state (see `complete_poll`/`finish_cancel`), and cleared, with
+/// `COMPLETE` set in its place, once that output is in place.
+const FINALIZING: usize = 0b1_0000;
+
+const REF_ONE: usize = 0b10_0000;
+const REF_SHIFT: u32 = 5;
+
+/// A snapshot of a task's packed lifecycle state, returned by the
+/// operations below so callers can decide what to do next without a
+/// separate atomic load.
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub(crate) struct Snapshot(usize);
+
+impl Snapshot {
+    pub(crate) fn is_running(self) -> bool {
+        self.0 & RUNNING == RUNNING
+    }
+
+    pub(crate) fn is_complete(self) -> bool {
+        self.0 & COMPLETE == COMPLETE
+    }
+
+    pub(crate) fn is_scheduled(self) -> bool {
+        self.0 & SCHEDULED == SCHEDULED
+    }
+
+    pub(crate) fn is_closed(self) -> bool {
+        self.0 & CLOSED == CLOSED
+    }
+
+    pub(crate) fn is_finalizing(self) -> bool {
+        self.0 & FINALIZING == FINALIZING
+    }
+
+    pub(crate) fn ref_count(self) -> usize {
+        self.0 >> REF_SHIFT
+    }
+}
+
+/// The outcome of finishing a poll, used by the polling harness to decide
+/// whether the caller should immediately poll the task again.
+pub(crate) enum PollResult {
+    /// The future completed; the caller must now release the task.
+    Complete,
+    /// `JoinHandle::abort` closed the task as part of the same transition
+    /// that ended this poll; the caller must write the task's cancelled
+    /// output and call `finish_cancel` to actually finish it, instead of
+    /// acting on `Complete`/`Yielded`/`Idle`. Reported here, rather than
+    /// through a separate load taken before the transition, so there is no
+    /// window between "the poll decided what to do" and "the poll committed
+    /// it" for a concurrent `abort` to fall into. `complete_poll` sets
+    /// `FINALIZING`, not `COMPLETE`, when it returns this, so nothing can
+    /// observe the task as complete before the caller finishes writing its
+    /// output and calls `finish_cancel`.
+    Closed,
+    /// The task woke itself while being polled and must be polled again.
+    Yielded,
+    /// The task is now idle, parked on some externally held waker.
+    Idle,
+}
+
+/// A task's lifecycle bits and ref count, packed into a single word so
+/// every transition is a single CAS, plus a separate count of the task's
+/// currently live `Waker`s. Mirrors the `async-task` state machine this
+/// task system is modeled on.
+pub(crate) struct State {
+    val: AtomicUsize,
+    /// Number of `Waker`s currently cloned from this task (including the
+    /// one handed to the future for the duration of each poll). Tracked
+    /// separately from the ref count so that the last one being dropped
+    /// while the task is pending can be detected and the task
+    /// rescheduled, instead of leaking it forever with no way to wake it.
+    wakers: AtomicUsize,
+}
+
+impl State {
+    /// State for a freshly constructed background task: one reference (the
+    /// run queue slot), and already marked scheduled so the first `run`
+    /// polls it unconditionally.
+    pub(crate) fn new_background() -> State {
+        State {
+            val: AtomicUsize::new(SCHEDULED | REF_ONE),
+            wakers: AtomicUsize::new(0),
+        }
+    }
+
+    /// State for a freshly constructed joinable task: two references, the
+    /// run queue slot and the `JoinHandle`.
+    pub(crate) fn new_joinable() -> State {
+        State {
+            val: AtomicUsize::new(SCHEDULED | (REF_ONE * 2)),
+            wakers: AtomicUsize::new(0),
+        }
+    }
+
+    pub(crate) fn load(&self) -> Snapshot {
+        Snapshot(self.val.load(Ordering::Acquire))
+    }
+
+    fn fetch_update(&self, mut f: impl FnMut(Snapshot) -> Snapshot) -> (Snapshot, Snapshot) {
+        let mut curr = self.val.load(Ordering::Acquire);
+        loop {
+            let next = f(Snapshot(curr)).0;
+            match self
+                .val
+                .compare_exchange_weak(curr, next, Ordering::AcqRel, Ordering::Acquire)
+            {
+                Ok(_) => return (Snapshot(curr), Snapshot(next)),
+                Err(actual) => curr = actual,
+            }
+        }
+    }
+
+    /// Transition out of `SCHEDULED` and into `RUNNING`. Called once at the
+    /// start of every poll; returns the state as observed just before the
+    /// transition, so the caller can tell whether the task was already
+    /// complete or closed.
+    pub(crate) fn start_poll(&self) -> Snapshot {
+        let (prev, _) = self.fetch_update(|s| Snapshot((s.0 & !SCHEDULED) | RUNNING));
+        prev
+    }
+
+    /// Called once a poll returns, with whether the future is now finished.
+    /// Whether `JoinHandle::abort` closed the task is decided as part of the
+    /// same CAS that clears `RUNNING`, so a caller deciding what to do with
+    /// the poll's result is never acting on a closed-ness snapshot taken
+    /// before this transition committed. If closed, this sets `FINALIZING`
+    /// rather than `COMPLETE` (see `PollResult::Closed`): the caller must
+    /// write the cancelled output and then call `finish_cancel`, which is
+    /// what actually publishes `COMPLETE`. `transition_to_scheduled` refuses
+    /// to hand out scheduling responsibility while `FINALIZING` is set, so a
+    /// waker racing with this can't mint a second claim on the same
+    /// reference before `finish_cancel`/`release` have had their say.
+    pub(crate) fn complete_poll(&self, is_ready: bool) -> PollResult {
+        let (_, next) = self.fetch_update(|s| {
+            if s.is_closed() {
+                Snapshot((s.0 & !RUNNING) | FINALIZING)
+            } else if is_ready {
+                Snapshot((s.0 & !RUNNING) | COMPLETE)
+            } else {
+                Snapshot(s.0 & !RUNNING)
+            }
+        });
+
+        if next.is_finalizing() {
+            PollResult::Closed
+        } else if is_ready {
+            PollResult::Complete
+        } else if next.is_scheduled() {
+            PollResult::Yielded
+        } else {
+            PollResult::Idle
+        }
+    }
+
+    /// Commit a task reported `PollResult::Closed` by `complete_poll` to
+    /// actually complete, once the caller has finished writing its
+    /// cancelled output. Clears `FINALIZING` and sets `COMPLETE` in one CAS,
+    /// so `COMPLETE` never becomes visible before that output does.
+    pub(crate) fn finish_cancel(&self) {
+        self.fetch_update(|s| Snapshot((s.0 & !FINALIZING) | COMPLETE));
+    }
+
+    /// Force the task directly to `COMPLETE`, bypassing the
+    /// `complete_poll`/`finish_cancel` hand-off. Only safe to call where the
+    /// caller already has exclusive access to the task with no concurrent
+    /// poller left to race against `FINALIZING` (executor shutdown, which
+    /// drains every task it still owns).
+    pub(crate) fn force_complete(&self) {
+        self.fetch_update(|s| Snapshot((s.0 & !RUNNING) | COMPLETE));
+    }
+
+    /// Attempt to transition the task into the scheduled state as part of
+    /// waking it. Returns `true` if the caller (a waker) is now responsible
+    /// for handing the task to `Schedule::schedule`. Refuses while the task
+    /// is complete, already scheduled, or being finalized by a poll that
+    /// just found it closed (see `complete_poll`), so a wake racing with
+    /// that finalization can't double-claim the same reference. While the
+    /// task is being polled, only `SCHEDULED` is set and `false` is
+    /// returned: the in-progress poll sees it in `complete_poll` and polls
+    /// again (`PollResult::Yielded`).
+    pub(crate) fn transition_to_scheduled(&self) -> bool {
+        let mut became_responsible = false;
+        self.fetch_update(|s| {
+            became_responsible = false;
+            if s.is_complete() || s.is_scheduled() || s.is_finalizing() {
+                s
+            } else if s.is_running() {
+                Snapshot(s.0 | SCHEDULED)
+            } else {
+                became_responsible = true;
+                Snapshot(s.0 | SCHEDULED)
+            }
+        });
+        became_responsible
+    }
+
+    /// Mark the task closed, as part of `JoinHandle::abort`. Idempotent.
+    /// Returns the state as observed just before the transition, so the
+    /// caller can tell whether the task was already running, scheduled or
+    /// complete.
+    pub(crate) fn close(&self) -> Snapshot {
+        let (prev, _) = self.fetch_update(|s| Snapshot(s.0 | CLOSED));
+        prev
+    }
+
+    pub(crate) fn ref_inc(&self) {
+        self.val.fetch_add(REF_ONE, Ordering::Relaxed);
+    }
+
+    /// Decrement the ref count, returning `true` if this was the last
+    /// reference, meaning the caller must deallocate the task.
+    pub(crate) fn ref_dec(&self) -> bool {
+        let prev = self.val.fetch_sub(REF_ONE, Ordering::AcqRel);
+        (prev >> REF_SHIFT) == 1
+    }
+
+    pub(crate) fn waker_inc(&self) {
+        self.wakers.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Decrement the live waker count. Returns `true` if this was the last
+    /// live waker and `transition_to_scheduled` made the caller responsible
+    /// for the task (it was idle: not complete, scheduled, being polled or
+    /// being finalized), in which case it has now been marked scheduled:
+    /// the caller (the waker being dropped) is responsible for handing it to
+    /// `Schedule::schedule` itself, so a task with no way left to wake it
+    /// is polled one final time instead of being stranded forever.
+    pub(crate) fn waker_dec(&self) -> bool {
+        let prev = self.wakers.fetch_sub(1, Ordering::AcqRel);
+        if prev != 1 {
+            return false;
+        }
+        self.transition_to_scheduled()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_background_has_one_ref_and_is_scheduled() {
+        let state = State::new_background();
+        let snapshot = state.load();
+        assert_eq!(snapshot.ref_count(), 1);
+        assert!(snapshot.is_scheduled());
+        assert!(!snapshot.is_running());
+        assert!(!snapshot.is_complete());
+    }
+
+    #[test]
+    fn new_joinable_has_two_refs() {
+        let state = State::new_joinable();
+        assert_eq!(state.load().ref_count(), 2);
+    }
+
+    #[test]
+    fn start_and_complete_poll_ready() {
+        let state = State::new_background();
+        let prev = state.start_poll();
+        assert!(prev.is_scheduled());
+        assert!(matches!(state.complete_poll(true), PollResult::Complete));
+        assert!(state.load().is_complete());
+    }
+}
@@ -0,0 +1,273 @@
+use super::*;
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+struct NoopSchedule;
+
+impl Schedule<SendMarker, &'static str> for NoopSchedule {
+    fn bind(&self, _task: &Task<Self, SendMarker, &'static str>) {}
+    fn release(&self, _task: Task<Self, SendMarker, &'static str>) {}
+    fn release_local(&self, _task: &Task<Self, SendMarker, &'static str>) {}
+    fn schedule(&self, _task: Task<Self, SendMarker, &'static str>) {}
+}
+
+impl Schedule<SendMarker, ()> for NoopSchedule {
+    fn bind(&self, _task: &Task<Self, SendMarker, ()>) {}
+    fn release(&self, _task: Task<Self, SendMarker, ()>) {}
+    fn release_local(&self, _task: &Task<Self, SendMarker, ()>) {}
+    fn schedule(&self, _task: Task<Self, SendMarker, ()>) {}
+}
+
+/// Poll `task` once, handing out a pointer to `scheduler` the first time a
+/// scheduler is requested, as the real run loop would.
+unsafe fn poll_once<Tag: 'static>(task: Task<NoopSchedule, SendMarker, Tag>, scheduler: &NoopSchedule) {
+    let outcome = task
+        .raw
+        .poll(&mut || NonNull::new(scheduler as *const NoopSchedule as *mut ()));
+    if outcome == PollOutcome::Done {
+        // The poll already released (or parked) the reference `task` holds.
+        std::mem::forget(task);
+    }
+}
+
+fn noop_waker() -> std::task::Waker {
+    fn clone(_: *const ()) -> std::task::RawWaker {
+        raw()
+    }
+    fn noop(_: *const ()) {}
+    fn raw() -> std::task::RawWaker {
+        static VTABLE: std::task::RawWakerVTable =
+            std::task::RawWakerVTable::new(clone, noop, noop, noop);
+        std::task::RawWaker::new(std::ptr::null(), &VTABLE)
+    }
+    unsafe { std::task::Waker::from_raw(raw()) }
+}
+
+#[test]
+fn background_task_preserves_tag_until_drop() {
+    let task: Task<NoopSchedule, SendMarker, &'static str> =
+        background::<_, NoopSchedule, _>(async {}, "priority:high");
+
+    assert_eq!(*task.header().tag, "priority:high");
+
+    drop(task);
+}
+
+#[test]
+fn joinable_task_preserves_tag_until_drop() {
+    let (task, handle) = joinable::<_, NoopSchedule, _>(async {}, "subsystem:io");
+
+    assert_eq!(*task.header().tag, "subsystem:io");
+    assert_eq!(*handle.tag(), "subsystem:io");
+
+    drop(task);
+    drop(handle);
+}
+
+#[test]
+fn abort_before_poll_drops_future_without_polling() {
+    let polled = Arc::new(AtomicBool::new(false));
+    let polled2 = polled.clone();
+
+    let (task, mut handle) = joinable::<_, NoopSchedule, _>(
+        async move {
+            polled2.store(true, Ordering::SeqCst);
+        },
+        (),
+    );
+
+    handle.abort();
+
+    let scheduler = NoopSchedule;
+    unsafe { poll_once(task, &scheduler) };
+
+    assert!(!polled.load(Ordering::SeqCst));
+
+    let waker = noop_waker();
+    let mut cx = Context::from_waker(&waker);
+    match Pin::new(&mut handle).poll(&mut cx) {
+        Poll::Ready(Err(e)) => assert!(e.is_cancelled()),
+        Poll::Ready(Ok(())) => panic!("expected a cancelled JoinError, got Ok"),
+        Poll::Pending => panic!("expected the handle to resolve immediately"),
+    }
+}
+
+#[test]
+fn abort_during_poll_cancels_without_leaking_the_future() {
+    struct AbortSelf {
+        handle: Arc<Mutex<Option<JoinHandle<(), ()>>>>,
+        polled: Arc<AtomicBool>,
+    }
+
+    impl Future for AbortSelf {
+        type Output = ();
+
+        fn poll(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<()> {
+            self.polled.store(true, Ordering::SeqCst);
+            if let Some(handle) = self.handle.lock().unwrap().as_ref() {
+                handle.abort();
+            }
+            Poll::Pending
+        }
+    }
+
+    let polled = Arc::new(AtomicBool::new(false));
+    let handle_slot = Arc::new(Mutex::new(None));
+
+    let (task, handle) = joinable::<_, NoopSchedule, _>(
+        AbortSelf {
+            handle: handle_slot.clone(),
+            polled: polled.clone(),
+        },
+        (),
+    );
+    *handle_slot.lock().unwrap() = Some(handle);
+
+    let scheduler = NoopSchedule;
+    unsafe { poll_once(task, &scheduler) };
+
+    assert!(polled.load(Ordering::SeqCst));
+
+    let mut handle = handle_slot.lock().unwrap().take().unwrap();
+    let waker = noop_waker();
+    let mut cx = Context::from_waker(&waker);
+    match Pin::new(&mut handle).poll(&mut cx) {
+        Poll::Ready(Err(e)) => assert!(e.is_cancelled()),
+        Poll::Ready(Ok(())) => panic!("expected a cancelled JoinError, got Ok"),
+        Poll::Pending => panic!("expected the handle to resolve immediately"),
+    }
+}
+
+#[test]
+fn abort_after_completion_is_a_no_op() {
+    let (task, mut handle) = joinable::<_, NoopSchedule, _>(async { 7u32 }, ());
+
+    let scheduler = NoopSchedule;
+    unsafe { poll_once(task, &scheduler) };
+
+    handle.abort();
+
+    let waker = noop_waker();
+    let mut cx = Context::from_waker(&waker);
+    match Pin::new(&mut handle).poll(&mut cx) {
+        Poll::Ready(Ok(7)) => {}
+        other => panic!("expected the original output, got {:?}", other.is_ready()),
+    }
+}
+
+#[test]
+fn dropping_the_last_waker_while_pending_reschedules_the_task_once() {
+    struct DropFlag(Arc<AtomicBool>);
+
+    impl Drop for DropFlag {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    struct StoreWakerThenPend {
+        slot: Arc<Mutex<Option<std::task::Waker>>>,
+        _flag: DropFlag,
+    }
+
+    impl Future for StoreWakerThenPend {
+        type Output = ();
+
+        fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+            *self.slot.lock().unwrap() = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+
+    struct CountingSchedule {
+        scheduled: AtomicUsize,
+    }
+
+    impl Schedule<SendMarker, ()> for CountingSchedule {
+        fn bind(&self, _task: &Task<Self, SendMarker, ()>) {}
+        fn release(&self, _task: Task<Self, SendMarker, ()>) {}
+        fn release_local(&self, _task: &Task<Self, SendMarker, ()>) {}
+        fn schedule(&self, task: Task<Self, SendMarker, ()>) {
+            // Stand in for the executor taking the task back: dropping it
+            // here, without polling it again, gives up the reference the
+            // task held while it sat idle.
+            self.scheduled.fetch_add(1, Ordering::SeqCst);
+            drop(task);
+        }
+    }
+
+    let dropped = Arc::new(AtomicBool::new(false));
+    let slot = Arc::new(Mutex::new(None));
+
+    let (task, handle) = joinable::<_, CountingSchedule, _>(
+        StoreWakerThenPend {
+            slot: slot.clone(),
+            _flag: DropFlag(dropped.clone()),
+        },
+        (),
+    );
+
+    let scheduler = CountingSchedule {
+        scheduled: AtomicUsize::new(0),
+    };
+
+    assert!(task
+        .run(DEFAULT_BUDGET, || Some(NonNull::from(&scheduler)))
+        .is_none());
+    assert!(!dropped.load(Ordering::SeqCst));
+
+    // The future stored exactly one waker and never will be told to wake
+    // again through it; dropping that waker must reschedule the task so
+    // it's released instead of leaking it forever.
+    slot.lock().unwrap().take();
+
+    assert_eq!(scheduler.scheduled.load(Ordering::SeqCst), 1);
+    assert!(!dropped.load(Ordering::SeqCst));
+
+    drop(handle);
+    assert!(dropped.load(Ordering::SeqCst));
+}
+
+#[test]
+fn budget_exhaustion_preempts_a_continuously_ready_task() {
+    struct AlwaysWakeSelf {
+        polls: Arc<AtomicUsize>,
+    }
+
+    impl Future for AlwaysWakeSelf {
+        type Output = ();
+
+        fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+            self.polls.fetch_add(1, Ordering::SeqCst);
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+
+    let polls = Arc::new(AtomicUsize::new(0));
+    let task: Task<NoopSchedule, SendMarker, ()> = background::<_, NoopSchedule, _>(
+        AlwaysWakeSelf {
+            polls: polls.clone(),
+        },
+        (),
+    );
+
+    let scheduler = NoopSchedule;
+    let budget = 5;
+    let requeued = task.run(budget, || {
+        NonNull::new(&scheduler as *const NoopSchedule as *mut ())
+    });
+
+    assert!(
+        requeued.is_some(),
+        "a continuously-ready task must be handed back to the caller once its budget runs out"
+    );
+    // The first poll is unconditional; after that, each unit of budget
+    // buys exactly one more immediate re-poll before the budget check
+    // finally refuses and forces a requeue instead.
+    assert_eq!(polls.load(Ordering::SeqCst), budget + 1);
+}
@@ -0,0 +1,64 @@
+use crate::executor::task::core::Header;
+
+use std::ptr::NonNull;
+use std::task::{RawWaker, RawWakerVTable, Waker};
+
+/// Build a new, independent `Waker` backed by the task at `header`,
+/// accounting for it in both the task's ref count and its live waker count.
+///
+/// # Safety
+///
+/// `header` must point at a live task.
+pub(crate) unsafe fn waker<Tag: 'static>(header: NonNull<Header<Tag>>) -> Waker {
+    header.as_ref().state.ref_inc();
+    header.as_ref().state.waker_inc();
+    Waker::from_raw(raw_waker(header))
+}
+
+fn raw_waker<Tag: 'static>(header: NonNull<Header<Tag>>) -> RawWaker {
+    RawWaker::new(header.as_ptr() as *const (), vtable::<Tag>())
+}
+
+fn vtable<Tag: 'static>() -> &'static RawWakerVTable {
+    const VTABLE: RawWakerVTable = RawWakerVTable::new(
+        clone_waker::<Tag>,
+        wake::<Tag>,
+        wake_by_ref::<Tag>,
+        drop_waker::<Tag>,
+    );
+    &VTABLE
+}
+
+unsafe fn clone_waker<Tag: 'static>(ptr: *const ()) -> RawWaker {
+    let header = NonNull::new_unchecked(ptr as *mut Header<Tag>);
+    header.as_ref().state.ref_inc();
+    header.as_ref().state.waker_inc();
+    raw_waker(header)
+}
+
+unsafe fn wake<Tag: 'static>(ptr: *const ()) {
+    wake_by_ref::<Tag>(ptr);
+    drop_waker::<Tag>(ptr);
+}
+
+unsafe fn wake_by_ref<Tag: 'static>(ptr: *const ()) {
+    let header = NonNull::new_unchecked(ptr as *mut Header<Tag>);
+    if header.as_ref().state.transition_to_scheduled() {
+        (header.as_ref().vtable.schedule)(header);
+    }
+}
+
+/// Dropping a `Waker` releases both its ref count unit and its live waker
+/// unit. If it was the last live waker and other references to the task
+/// remain (so there is still a task for the scheduler to hand back), the
+/// task is rescheduled one final time instead of being left with no way
+/// to ever wake it again.
+unsafe fn drop_waker<Tag: 'static>(ptr: *const ()) {
+    let header = NonNull::new_unchecked(ptr as *mut Header<Tag>);
+    let last_waker = header.as_ref().state.waker_dec();
+    if header.as_ref().state.ref_dec() {
+        (header.as_ref().vtable.dealloc)(header);
+    } else if last_waker {
+        (header.as_ref().vtable.schedule)(header);
+    }
+}